@@ -36,7 +36,7 @@ fn fetch_messages_and_idle(server: &str, login: &str, password: &str) -> imap::e
         loop {
             let res = match imap_session.idle() {
                 Ok(mut idle) => {
-                    &idle.set_keepalive(Duration::from_secs(20));
+                    idle.set_keepalive(Duration::from_secs(20));
                     println!("entering idle wait_keepalive");
                     idle.wait_keepalive()
                 }
@@ -45,11 +45,15 @@ fn fetch_messages_and_idle(server: &str, login: &str, password: &str) -> imap::e
                 }
             };
             match res {
-                Ok(true) => {
-                    println!("wait_keepalive returned data, idle-finished");
+                Ok(responses) if !responses.is_empty() => {
+                    println!(
+                        "wait_keepalive returned {} response(s): {:?}",
+                        responses.len(),
+                        responses
+                    );
                     break;
                 }
-                Ok(false) => {
+                Ok(_) => {
                     println!("wait_keepalive returned no data, let's re-enter idle");
                 }
                 Err(err) => {
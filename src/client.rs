@@ -0,0 +1,347 @@
+//! The IMAP client and session types.
+
+use crate::error::{Error, Result};
+use crate::extensions::idle::Handle;
+use crate::types::{Capabilities, UnsolicitedResponse};
+use std::io::{self, Read, Write};
+use std::result;
+
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+use std::net::{TcpStream, ToSocketAddrs};
+
+#[cfg(feature = "tls")]
+use native_tls::{TlsConnector, TlsStream};
+
+#[cfg(feature = "rustls-tls")]
+use rustls::{ClientConfig, ClientConnection, StreamOwned};
+#[cfg(feature = "rustls-tls")]
+use std::convert::TryInto;
+#[cfg(feature = "rustls-tls")]
+use std::sync::Arc;
+
+/// A thin wrapper around a transport that lets callers get back the raw stream, e.g. so the
+/// `idle` extension can install read timeouts on it, or poll its raw fd, or so a caller that
+/// hit an unrecoverable error can reach through to shut the socket down directly.
+#[derive(Debug)]
+pub struct Stream<T> {
+    inner: T,
+}
+
+impl<T> Stream<T> {
+    /// Get a reference to the underlying transport.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying transport.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: Read> Stream<T> {
+    /// Read a single line (up to and including the trailing `\n`) into `buf`.
+    fn read_line(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let mut total = 0;
+        let mut byte = [0u8; 1];
+        loop {
+            let n = self.inner.read(&mut byte)?;
+            if n == 0 {
+                break;
+            }
+            buf.push(byte[0]);
+            total += 1;
+            if byte[0] == b'\n' {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Read exactly `buf.len()` raw bytes, e.g. the bytes of an IMAP literal.
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf)
+    }
+}
+
+/// An unauthenticated connection to an IMAP server.
+///
+/// Call [`Client::login`] to turn this into an authenticated [`Session`].
+#[derive(Debug)]
+pub struct Client<T: Read + Write> {
+    session: Session<T>,
+}
+
+impl<T: Read + Write> Client<T> {
+    #[cfg(any(feature = "tls", feature = "rustls-tls"))]
+    pub(crate) fn new(stream: T) -> Self {
+        Client {
+            session: Session::new(stream),
+        }
+    }
+
+    /// Log in with the given username and password, turning this `Client` into a [`Session`].
+    ///
+    /// On failure the original `Client` is handed back alongside the error, so the caller can
+    /// retry or clean up the connection.
+    pub fn login<U: AsRef<str>, P: AsRef<str>>(
+        mut self,
+        username: U,
+        password: P,
+    ) -> result::Result<Session<T>, (Error, Client<T>)> {
+        let command = format!("LOGIN {} {}", username.as_ref(), password.as_ref());
+        if let Err(e) = self.session.run_command(&command) {
+            return Err((e, self));
+        }
+        match self.session.read_response() {
+            Ok(_) => Ok(self.session),
+            Err(e) => Err((e, self)),
+        }
+    }
+}
+
+impl<T: Read + Write> std::ops::Deref for Client<T> {
+    type Target = Session<T>;
+
+    fn deref(&self) -> &Session<T> {
+        &self.session
+    }
+}
+
+impl<T: Read + Write> std::ops::DerefMut for Client<T> {
+    fn deref_mut(&mut self) -> &mut Session<T> {
+        &mut self.session
+    }
+}
+
+/// An authenticated connection to an IMAP server.
+#[derive(Debug)]
+pub struct Session<T: Read + Write> {
+    pub stream: Stream<T>,
+    tag: u32,
+    /// Whether to print the raw protocol exchange with the server to stderr, for debugging.
+    pub debug: bool,
+}
+
+impl<T: Read + Write> Session<T> {
+    #[cfg(any(feature = "tls", feature = "rustls-tls", test))]
+    pub(crate) fn new(stream: T) -> Self {
+        Session {
+            stream: Stream { inner: stream },
+            tag: 0,
+            debug: false,
+        }
+    }
+
+    fn next_tag(&mut self) -> String {
+        self.tag += 1;
+        format!("a{}", self.tag)
+    }
+
+    pub(crate) fn write_line(&mut self, buf: &[u8]) -> Result<()> {
+        if self.debug {
+            eprintln!("C: {}", String::from_utf8_lossy(buf));
+        }
+        self.stream.get_mut().write_all(buf)?;
+        self.stream.get_mut().write_all(b"\r\n")?;
+        self.stream.get_mut().flush()?;
+        Ok(())
+    }
+
+    /// Send a tagged command to the server. This does not wait for or read the response; use
+    /// [`Session::read_response`] (or, for IDLE, [`Session::readline`]) for that.
+    pub(crate) fn run_command(&mut self, command: &str) -> Result<()> {
+        let tag = self.next_tag();
+        self.write_line(format!("{} {}", tag, command).as_bytes())
+    }
+
+    /// Read a single line of the server's response into `buf`.
+    ///
+    /// An elapsed read timeout is reported as `ErrorKind::TimedOut`, including on Unix, where
+    /// the socket itself reports it as `WouldBlock`.
+    pub(crate) fn readline(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+        let n = self.stream.read_line(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock => io::Error::new(io::ErrorKind::TimedOut, e),
+            _ => e,
+        })?;
+        if n == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "server closed the connection",
+            )));
+        }
+        if self.debug {
+            eprintln!("S: {}", String::from_utf8_lossy(buf));
+        }
+        Ok(n)
+    }
+
+    /// Read lines from the server until (and including) the tagged completion response, i.e.
+    /// every untagged (`* ...`) line that precedes it.
+    ///
+    /// A tagged `NO` or `BAD` completion is returned as [`Error::No`] or [`Error::Bad`].
+    pub(crate) fn read_response_onto(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        loop {
+            let start = buf.len();
+            self.readline(buf)?;
+            if !buf[start..].starts_with(b"* ") {
+                let line = String::from_utf8_lossy(&buf[start..]).trim_end().to_string();
+                match line.split(' ').nth(1) {
+                    Some("NO") => return Err(Error::No(line)),
+                    Some("BAD") => return Err(Error::Bad(line)),
+                    _ => break,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read_response(&mut self) -> Result<Vec<u8>> {
+        let mut v = Vec::new();
+        self.read_response_onto(&mut v)?;
+        Ok(v)
+    }
+
+    /// Read exactly `buf.len()` raw bytes off the wire, e.g. the bytes of an IMAP literal
+    /// (`{n}` syntax) embedded in a response.
+    pub(crate) fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.stream.read_exact(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock => io::Error::new(io::ErrorKind::TimedOut, e),
+            _ => e,
+        })?;
+        if self.debug {
+            eprintln!("S: <{} literal bytes>", buf.len());
+        }
+        Ok(())
+    }
+
+    /// Ask the server for its capabilities.
+    pub fn capabilities(&mut self) -> Result<Capabilities> {
+        self.run_command("CAPABILITY")?;
+        let mut caps = Vec::new();
+        loop {
+            let mut v = Vec::new();
+            self.readline(&mut v)?;
+            if let Some(rest) = std::str::from_utf8(&v)
+                .ok()
+                .and_then(|l| l.trim_end().strip_prefix("* CAPABILITY "))
+            {
+                caps.extend(rest.split_whitespace().map(str::to_string));
+            } else if !v.starts_with(b"*") {
+                break;
+            }
+        }
+        Ok(Capabilities(caps))
+    }
+
+    /// Select a mailbox, making it the target of subsequent commands.
+    pub fn select(&mut self, mailbox: &str) -> Result<()> {
+        self.run_command(&format!("SELECT {}", mailbox))?;
+        self.read_response().map(|_| ())
+    }
+
+    /// Fetch the messages in `sequence_set`, requesting the given data items (e.g. `"RFC822"`).
+    ///
+    /// Each untagged `FETCH` response the server sends back is returned as an
+    /// [`UnsolicitedResponse`].
+    pub fn fetch(&mut self, sequence_set: &str, query: &str) -> Result<Vec<UnsolicitedResponse>> {
+        self.run_command(&format!("FETCH {} {}", sequence_set, query))?;
+        let mut responses = Vec::new();
+        loop {
+            let mut v = Vec::new();
+            self.readline(&mut v)?;
+            if v.starts_with(b"* ") {
+                if let Some(resp) = UnsolicitedResponse::parse(&v) {
+                    responses.push(resp);
+                }
+            } else {
+                break;
+            }
+        }
+        Ok(responses)
+    }
+
+    /// Start an `IDLE` command, returning a [`Handle`] that can be used to wait for mailbox
+    /// changes.
+    pub fn idle(&mut self) -> Result<Handle<'_, T>> {
+        Handle::make(self)
+    }
+
+    /// Log out of the server, consuming the session.
+    pub fn logout(mut self) -> Result<()> {
+        self.run_command("LOGOUT")?;
+        self.read_response().map(|_| ())
+    }
+}
+
+/// Connect to `addr` using TLS (via `native-tls`), verifying the server's certificate against
+/// `domain`.
+#[cfg(feature = "tls")]
+pub fn connect<A: ToSocketAddrs, S: AsRef<str>>(
+    addr: A,
+    domain: S,
+    ssl_connector: &TlsConnector,
+) -> Result<Client<TlsStream<TcpStream>>> {
+    let tcp_stream = TcpStream::connect(addr)?;
+    let tls_stream = ssl_connector
+        .connect(domain.as_ref(), tcp_stream)
+        .map_err(|e| Error::Bad(e.to_string()))?;
+    let mut client = Client::new(tls_stream);
+    // The server greeting is a single untagged line, not a tagged completion.
+    client.session.readline(&mut Vec::new())?;
+    Ok(client)
+}
+
+/// Like [`connect`], but additionally installs `timeout` as the read timeout on the underlying
+/// `TcpStream` before the handshake even starts, so a server that doesn't respond during
+/// connection setup doesn't hang the caller forever.
+#[cfg(feature = "tls")]
+pub fn connect_timeout<A: ToSocketAddrs, S: AsRef<str>>(
+    addr: A,
+    domain: S,
+    ssl_connector: &TlsConnector,
+    timeout: Option<std::time::Duration>,
+) -> Result<Client<TlsStream<TcpStream>>> {
+    let tcp_stream = TcpStream::connect(addr)?;
+    tcp_stream.set_read_timeout(timeout)?;
+    let tls_stream = ssl_connector
+        .connect(domain.as_ref(), tcp_stream)
+        .map_err(|e| Error::Bad(e.to_string()))?;
+    let mut client = Client::new(tls_stream);
+    // The server greeting is a single untagged line, not a tagged completion.
+    client.session.readline(&mut Vec::new())?;
+    Ok(client)
+}
+
+/// Connect to `addr` using TLS (via `rustls`), verifying the server's certificate against
+/// `domain`.
+#[cfg(feature = "rustls-tls")]
+pub fn connect_rustls<A: ToSocketAddrs>(
+    addr: A,
+    domain: &str,
+    config: Arc<ClientConfig>,
+) -> Result<Client<StreamOwned<ClientConnection, TcpStream>>> {
+    connect_rustls_timeout(addr, domain, config, None)
+}
+
+/// Like [`connect_rustls`], but additionally installs `timeout` as the read timeout on the
+/// underlying `TcpStream` before the handshake even starts.
+#[cfg(feature = "rustls-tls")]
+pub fn connect_rustls_timeout<A: ToSocketAddrs>(
+    addr: A,
+    domain: &str,
+    config: Arc<ClientConfig>,
+    timeout: Option<std::time::Duration>,
+) -> Result<Client<StreamOwned<ClientConnection, TcpStream>>> {
+    let tcp_stream = TcpStream::connect(addr)?;
+    tcp_stream.set_read_timeout(timeout)?;
+    let server_name = domain
+        .try_into()
+        .map_err(|_| Error::Bad(format!("invalid domain name: {}", domain)))?;
+    let conn = ClientConnection::new(config, server_name).map_err(|e| Error::Bad(e.to_string()))?;
+    let tls_stream = StreamOwned::new(conn, tcp_stream);
+    let mut client = Client::new(tls_stream);
+    // The server greeting is a single untagged line, not a tagged completion.
+    client.session.readline(&mut Vec::new())?;
+    Ok(client)
+}
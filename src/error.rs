@@ -0,0 +1,48 @@
+//! IMAP error types.
+
+use std::fmt;
+use std::io;
+use std::result;
+
+/// A convenience wrapper around `std::result::Result`.
+pub type Result<T> = result::Result<T, Error>;
+
+/// An error occurring while talking to an IMAP server.
+#[derive(Debug)]
+pub enum Error {
+    /// An `io::Error` that occurred while reading from or writing to the underlying stream.
+    Io(io::Error),
+    /// The server returned a tagged `BAD` response, or the connection could not be set up (the
+    /// TLS handshake failed, or the domain name was invalid).
+    Bad(String),
+    /// The server returned a tagged `NO` response.
+    No(String),
+    /// A response from the server couldn't be parsed.
+    Parse(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Bad(s) => write!(f, "BAD response: {}", s),
+            Error::No(s) => write!(f, "NO response: {}", s),
+            Error::Parse(s) => write!(f, "could not parse server response: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Bad(_) | Error::No(_) | Error::Parse(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
@@ -3,12 +3,20 @@
 
 use crate::client::Session;
 use crate::error::{Error, Result};
+#[cfg(unix)]
+use crate::extensions::interrupt::Interrupt;
+use crate::types::UnsolicitedResponse;
 #[cfg(feature = "tls")]
 use native_tls::TlsStream;
+#[cfg(feature = "rustls-tls")]
+use rustls::{ClientConnection, StreamOwned};
 use std::io::{self, Read, Write};
 use std::net::TcpStream;
 use std::time::Duration;
 
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+
 /// `Handle` allows a client to block waiting for changes to the remote mailbox.
 ///
 /// The handle blocks using the [`IDLE` command](https://tools.ietf.org/html/rfc2177#section-3)
@@ -49,6 +57,129 @@ pub trait SetReadTimeout {
     fn read_timeout(&self) -> Result<Option<Duration>>;
 }
 
+/// Exposes the raw OS socket backing a transport, so it can be polled alongside other wakeup
+/// sources by [`Handle::wait_with_interrupt`].
+#[cfg(unix)]
+pub trait AsRawFd {
+    /// Returns the raw file descriptor of the underlying socket.
+    fn as_raw_fd(&self) -> RawFd;
+}
+
+/// If `line` ends with an IMAP literal marker (`{n}` right before the terminating `CRLF`),
+/// returns `n` -- the number of raw bytes that follow before the logical response line actually
+/// ends.
+fn trailing_literal_len(line: &[u8]) -> Option<usize> {
+    let s = std::str::from_utf8(line).ok()?;
+    let trimmed = s.trim_end_matches(['\r', '\n']);
+    let rest = trimmed.strip_suffix('}')?;
+    let start = rest.rfind('{')?;
+    rest[start + 1..].parse().ok()
+}
+
+/// The reason [`Handle::wait_with_interrupt`], [`Handle::wait_interval`] or
+/// [`Session::wait_interval`] returned.
+#[derive(Debug)]
+pub enum Stop {
+    /// The server sent one or more unsolicited responses.
+    Data(Vec<UnsolicitedResponse>),
+    /// The matching `Interruptor::stop` was called before the server reported anything.
+    Interrupted,
+    /// The timeout or poll interval elapsed without the server reporting anything.
+    TimedOut,
+}
+
+impl<T: SetReadTimeout + Read + Write> Session<T> {
+    /// Set (or clear) a read timeout applied to every command round-trip on this session, not
+    /// just IDLE.
+    ///
+    /// Because the timeout is installed directly on the underlying socket, it takes effect for
+    /// every subsequent read, including ordinary commands like `fetch`/`select`/`login`: a
+    /// server that stalls mid-response now surfaces as `Error::Io` with `ErrorKind::TimedOut`
+    /// instead of hanging forever. Passing `None` (the default) disables the timeout.
+    ///
+    /// [`Handle::wait_timeout`] and [`Handle::wait_keepalive`] save whatever timeout is
+    /// configured here and restore it once they're done, so the two don't clobber each other.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.stream.get_mut().set_read_timeout(timeout)
+    }
+
+    /// Wait for mailbox changes, guaranteeing a wakeup at least every `poll` interval even if
+    /// the server never sends anything on its own.
+    ///
+    /// If the server advertises the `IDLE` capability, this simply starts an IDLE and delegates
+    /// to [`Handle::wait_interval`]. Some servers don't support `IDLE` at all though, so
+    /// otherwise this falls back to polling: a `NOOP` is issued every `poll` interval and any
+    /// resulting unsolicited responses are parsed and returned, exactly as IDLE would report
+    /// them.
+    pub fn wait_interval(&mut self, poll: Duration) -> Result<Stop> {
+        let supports_idle = self
+            .capabilities()
+            .map(|caps| caps.has_str("IDLE"))
+            .unwrap_or(false);
+
+        if supports_idle {
+            return self.idle()?.wait_interval(poll);
+        }
+
+        // Without IDLE the server reports nothing unprompted, so wait out the interval first.
+        std::thread::sleep(poll);
+        let old_timeout = self.stream.get_mut().read_timeout()?;
+        self.stream.get_mut().set_read_timeout(Some(poll))?;
+        let result = self.poll_once();
+        self.stream.get_mut().set_read_timeout(old_timeout)?;
+        result
+    }
+
+    /// Issue a single `NOOP` and collect whatever unsolicited responses come back with it,
+    /// treating a read timeout as "nothing (more) changed" rather than an error.
+    ///
+    /// If the timeout fires partway through the response, any events already parsed from
+    /// earlier `* ...` lines are kept rather than discarded (mirroring `Handle::wait_inner`).
+    /// The rest of the `NOOP`'s response -- including its tagged completion line, which may
+    /// still be in flight -- is then drained with a generous fixed timeout, so that a command
+    /// issued afterwards on this `Session` can't mistake it for its own response.
+    fn poll_once(&mut self) -> Result<Stop> {
+        self.run_command("NOOP")?;
+        let mut responses = Vec::new();
+        loop {
+            let mut v = Vec::new();
+            match self.readline(&mut v) {
+                Err(Error::Io(ref e))
+                    if e.kind() == io::ErrorKind::TimedOut
+                        || e.kind() == io::ErrorKind::WouldBlock =>
+                {
+                    let old_timeout = self.stream.get_mut().read_timeout()?;
+                    self.stream
+                        .get_mut()
+                        .set_read_timeout(Some(Duration::from_secs(60)))?;
+                    let drained = self.read_response_onto(&mut Vec::new());
+                    self.stream.get_mut().set_read_timeout(old_timeout)?;
+                    drained?;
+                    return Ok(if responses.is_empty() {
+                        Stop::TimedOut
+                    } else {
+                        Stop::Data(responses)
+                    });
+                }
+                Err(err) => return Err(err),
+                Ok(_) if v.starts_with(b"* ") => {
+                    if let Some(resp) = UnsolicitedResponse::parse(&v) {
+                        responses.push(resp);
+                    }
+                }
+                Ok(_) => {
+                    // A non-`*`-prefixed line is the tagged completion of the `NOOP`.
+                    return Ok(if responses.is_empty() {
+                        Stop::TimedOut
+                    } else {
+                        Stop::Data(responses)
+                    });
+                }
+            }
+        }
+    }
+}
+
 impl<'a, T: Read + Write + 'a> Handle<'a, T> {
     pub(crate) fn make(session: &'a mut Session<T>) -> Result<Self> {
         let mut h = Handle {
@@ -94,32 +225,82 @@ impl<'a, T: Read + Write + 'a> Handle<'a, T> {
         }
     }
 
+    /// Read one full logical IMAP response line from the server, transparently reassembling any
+    /// embedded literal (`{n}` syntax, e.g. in `* 4 FETCH (BODY[] {123}\r\n...)`) so that a
+    /// multi-line response is never handed to [`UnsolicitedResponse::parse`] truncated partway
+    /// through: a single [`Session::readline`] call only ever returns one line off the wire, and
+    /// a line ending in a literal marker means more of the same logical response follows.
+    fn read_full_line(&mut self) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.session.readline(&mut buf)?;
+        while let Some(n) = trailing_literal_len(&buf) {
+            let mut literal = vec![0; n];
+            self.session.read_exact(&mut literal)?;
+            buf.extend_from_slice(&literal);
+            self.session.readline(&mut buf)?;
+        }
+        Ok(buf)
+    }
+
     /// Internal helper that doesn't consume self.
     ///
     /// This is necessary so that we can keep using the inner `Session` in `wait_keepalive`.
-    /// return Ok(true) if server reported data, Ok(false) if we ran
-    /// into a timeout but idle-waiting can continue.  Any error means
-    /// that the underlying stream was closed and a reconnect is neccessary
-    fn wait_inner(&mut self) -> Result<bool> {
-        let mut v = Vec::new();
-        match self.session.readline(&mut v) {
-            Err(Error::Io(ref e))
-                if e.kind() == io::ErrorKind::TimedOut || e.kind() == io::ErrorKind::WouldBlock =>
-            {
-                if self.session.debug {
-                    eprintln!("wait_inner got error {:?}", e);
+    ///
+    /// Reads untagged responses off the wire until either the given `until` callback asks us to
+    /// stop, or the read times out. Every response that parses as an [`UnsolicitedResponse`] is
+    /// collected and returned; the `* OK ... still here` keepalive lines servers send are parsed
+    /// as `None` and ignored so that they don't spuriously wake the caller.
+    ///
+    /// A timeout means idle-waiting can continue and is not an error; any other error means that
+    /// the underlying stream was closed and a reconnect is necessary.
+    fn wait_inner(
+        &mut self,
+        mut until: impl FnMut(&UnsolicitedResponse) -> bool,
+    ) -> Result<Vec<UnsolicitedResponse>> {
+        let mut responses = Vec::new();
+        loop {
+            match self.read_full_line() {
+                Err(Error::Io(ref e))
+                    if e.kind() == io::ErrorKind::TimedOut
+                        || e.kind() == io::ErrorKind::WouldBlock =>
+                {
+                    if self.session.debug {
+                        eprintln!("wait_inner got error {:?}", e);
+                    }
+                    self.terminate()?;
+                    return Ok(responses);
+                }
+                Err(err) => return Err(err),
+                Ok(v) => {
+                    if let Some(resp) = UnsolicitedResponse::parse(&v) {
+                        let keep_going = until(&resp);
+                        responses.push(resp);
+                        if !keep_going {
+                            return Ok(responses);
+                        }
+                    }
                 }
-                self.terminate()?;
-                Ok(false)
             }
-            Err(err) => Err(err),
-            Ok(_) => Ok(true),
         }
     }
 
-    /// Block until the selected mailbox changes.
-    pub fn wait(mut self) -> Result<bool> {
-        self.wait_inner()
+    /// Block until the selected mailbox changes, and return the unsolicited response that
+    /// reported the change.
+    ///
+    /// The wait ends at the first such response; responses the server sends after it are not
+    /// returned. Use [`Handle::wait_while`] to keep collecting.
+    pub fn wait(mut self) -> Result<Vec<UnsolicitedResponse>> {
+        self.wait_inner(|_| false)
+    }
+
+    /// Block until `until` returns `false` for an unsolicited response, or the connection times
+    /// out. `until` is called once for every [`UnsolicitedResponse`] the server sends, and
+    /// returning `true` keeps idling.
+    pub fn wait_while(
+        mut self,
+        until: impl FnMut(&UnsolicitedResponse) -> bool,
+    ) -> Result<Vec<UnsolicitedResponse>> {
+        self.wait_inner(until)
     }
 }
 
@@ -131,7 +312,11 @@ impl<'a, T: SetReadTimeout + Read + Write + 'a> Handle<'a, T> {
         self.keepalive = interval;
     }
 
-    /// Block until the selected mailbox changes.
+    /// Block until the selected mailbox changes, and return the unsolicited response that
+    /// reported the change (an empty list if the keepalive interval elapsed first).
+    ///
+    /// The wait ends at the first such response; responses the server sends after it are not
+    /// returned.
     ///
     /// This method differs from [`Handle::wait`] in that it will periodically refresh the IDLE
     /// connection, to prevent the server from timing out our connection. The keepalive interval is
@@ -139,7 +324,7 @@ impl<'a, T: SetReadTimeout + Read + Write + 'a> Handle<'a, T> {
     /// [`Handle::set_keepalive`].
     ///
     /// This is the recommended method to use for waiting.
-    pub fn wait_keepalive(self) -> Result<bool> {
+    pub fn wait_keepalive(self) -> Result<Vec<UnsolicitedResponse>> {
         // The server MAY consider a client inactive if it has an IDLE command
         // running, and if such a server has an inactivity timeout it MAY log
         // the client off implicitly at the end of its timeout period.  Because
@@ -152,35 +337,62 @@ impl<'a, T: SetReadTimeout + Read + Write + 'a> Handle<'a, T> {
     }
 
     /// Block until the selected mailbox changes, or until the given amount of time has expired.
-    pub fn wait_timeout(mut self, timeout: Duration) -> Result<bool> {
+    pub fn wait_timeout(mut self, timeout: Duration) -> Result<Vec<UnsolicitedResponse>> {
         self.old_timeout = self.session.stream.get_mut().read_timeout()?;
         self.session
             .stream
             .get_mut()
             .set_read_timeout(Some(timeout))?;
-        self.wait_inner_keepalive()
+        self.wait_inner_keepalive(|_| false)
     }
 
-    fn wait_inner_keepalive(&mut self) -> Result<bool> {
-        let mut v = Vec::new();
+    /// Like [`Handle::wait_timeout`], but reports whether the wakeup was caused by an actual
+    /// mailbox change or merely by `poll` elapsing, which is what callers that want "run every N
+    /// minutes even if no mail arrives" behavior need. Prefer [`Session::wait_interval`] unless
+    /// you already hold a `Handle`.
+    pub fn wait_interval(self, poll: Duration) -> Result<Stop> {
+        match self.wait_timeout(poll)? {
+            responses if responses.is_empty() => Ok(Stop::TimedOut),
+            responses => Ok(Stop::Data(responses)),
+        }
+    }
 
-        match self.session.readline(&mut v).map(|_| true) {
-            Err(Error::Io(ref e))
-                if e.kind() == io::ErrorKind::TimedOut || e.kind() == io::ErrorKind::WouldBlock =>
-            {
-                if self.session.debug {
-                    eprintln!("wait_inner got error {:?}", e);
+    fn wait_inner_keepalive(
+        &mut self,
+        mut until: impl FnMut(&UnsolicitedResponse) -> bool,
+    ) -> Result<Vec<UnsolicitedResponse>> {
+        let mut responses = Vec::new();
+        loop {
+            match self.read_full_line() {
+                Err(Error::Io(ref e))
+                    if e.kind() == io::ErrorKind::TimedOut
+                        || e.kind() == io::ErrorKind::WouldBlock =>
+                {
+                    if self.session.debug {
+                        eprintln!("wait_inner got error {:?}", e);
+                    }
+                    self.session
+                        .stream
+                        .get_mut()
+                        .set_read_timeout(Some(Duration::from_secs(60)))?;
+                    self.terminate()?;
+                    // Restore whatever application-level timeout (e.g. from
+                    // `Session::set_timeout`) was in effect before we clobbered it above and in
+                    // `wait_timeout`/`wait_interval`, so it doesn't leak into later commands.
+                    self.restore_timeout()?;
+                    return Ok(responses);
+                }
+                Err(err) => return Err(err),
+                Ok(v) => {
+                    if let Some(resp) = UnsolicitedResponse::parse(&v) {
+                        let keep_going = until(&resp);
+                        responses.push(resp);
+                        if !keep_going {
+                            self.restore_timeout()?;
+                            return Ok(responses);
+                        }
+                    }
                 }
-                self.session
-                    .stream
-                    .get_mut()
-                    .set_read_timeout(Some(Duration::from_secs(60)))?;
-                self.terminate()?;
-                Ok(false)
-            }
-            v => {
-                self.restore_timeout()?;
-                v
             }
         }
     }
@@ -193,6 +405,79 @@ impl<'a, T: SetReadTimeout + Read + Write + 'a> Handle<'a, T> {
     }
 }
 
+#[cfg(unix)]
+impl<'a, T: SetReadTimeout + AsRawFd + Read + Write + 'a> Handle<'a, T> {
+    /// Block until the selected mailbox changes, `interrupt` is signalled via its
+    /// [`Interruptor`](crate::extensions::interrupt::Interruptor), or `timeout` elapses.
+    ///
+    /// This polls the IMAP socket and the interrupt's wakeup fd together, so the wait can be
+    /// cancelled from another thread regardless of whether the server ever sends anything.
+    /// Whichever becomes readable first wins, and the interrupt wins if both are readable; if
+    /// neither does before `timeout`, this returns [`Stop::TimedOut`] (with a `timeout` of
+    /// `None` the poll blocks until one of them is readable). Either way we
+    /// always complete the `DONE` handshake before returning, so the underlying `Session` is
+    /// left in a clean, reusable state rather than stuck mid-IDLE.
+    pub fn wait_with_interrupt(
+        mut self,
+        interrupt: &Interrupt,
+        timeout: Option<Duration>,
+    ) -> Result<Stop> {
+        let sock_fd = self.session.stream.get_ref().as_raw_fd();
+        let mut fds = [
+            libc::pollfd {
+                fd: sock_fd,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: interrupt.read_fd,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+
+        // `poll` wants a timeout in milliseconds, with a negative value meaning "block
+        // forever".
+        let poll_timeout_ms = match timeout {
+            Some(d) => d.as_millis().min(i32::MAX as u128) as i32,
+            None => -1,
+        };
+
+        // Block until the IMAP socket or the interrupt's self-pipe becomes readable, or
+        // `timeout` elapses.
+        let ready =
+            unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, poll_timeout_ms) };
+        if ready < 0 {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+        if ready == 0 {
+            self.terminate()?;
+            return Ok(Stop::TimedOut);
+        }
+
+        if fds[1].revents & libc::POLLIN != 0 {
+            // Drain the self-pipe completely (it's non-blocking, so this stops as soon as
+            // there's nothing left to read) so that bytes written by a burst of `stop()` calls
+            // don't linger and wake an unrelated, later wait.
+            let mut buf = [0u8; 64];
+            loop {
+                let n =
+                    unsafe { libc::read(interrupt.read_fd, buf.as_mut_ptr() as *mut _, buf.len()) };
+                if n <= 0 {
+                    break;
+                }
+            }
+            self.terminate()?;
+            return Ok(Stop::Interrupted);
+        }
+
+        match self.wait_inner(|_| false)? {
+            responses if responses.is_empty() => Ok(Stop::TimedOut),
+            responses => Ok(Stop::Data(responses)),
+        }
+    }
+}
+
 impl<'a, T: Read + Write + 'a> Drop for Handle<'a, T> {
     fn drop(&mut self) {
         // we don't want to panic here if we can't terminate the Idle
@@ -200,7 +485,7 @@ impl<'a, T: Read + Write + 'a> Drop for Handle<'a, T> {
     }
 }
 
-impl<'a> SetReadTimeout for TcpStream {
+impl SetReadTimeout for TcpStream {
     fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
         TcpStream::set_read_timeout(self, timeout).map_err(Error::Io)
     }
@@ -211,7 +496,7 @@ impl<'a> SetReadTimeout for TcpStream {
 }
 
 #[cfg(feature = "tls")]
-impl<'a> SetReadTimeout for TlsStream<TcpStream> {
+impl SetReadTimeout for TlsStream<TcpStream> {
     fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
         self.get_ref().set_read_timeout(timeout).map_err(Error::Io)
     }
@@ -219,3 +504,138 @@ impl<'a> SetReadTimeout for TlsStream<TcpStream> {
         self.get_ref().read_timeout().map_err(Error::Io)
     }
 }
+
+// Like the `native-tls` impl above, the read timeout applies to the `TcpStream` underneath the
+// TLS session, since rustls itself has no notion of timeouts -- it just reads whatever the
+// wrapped transport gives it.
+#[cfg(feature = "rustls-tls")]
+impl SetReadTimeout for StreamOwned<ClientConnection, TcpStream> {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.sock.set_read_timeout(timeout).map_err(Error::Io)
+    }
+
+    fn read_timeout(&self) -> Result<Option<Duration>> {
+        self.sock.read_timeout().map_err(Error::Io)
+    }
+}
+
+#[cfg(all(unix, feature = "rustls-tls"))]
+impl AsRawFd for StreamOwned<ClientConnection, TcpStream> {
+    fn as_raw_fd(&self) -> RawFd {
+        std::os::unix::io::AsRawFd::as_raw_fd(&self.sock)
+    }
+}
+
+#[cfg(unix)]
+impl AsRawFd for TcpStream {
+    fn as_raw_fd(&self) -> RawFd {
+        std::os::unix::io::AsRawFd::as_raw_fd(self)
+    }
+}
+
+#[cfg(all(unix, feature = "tls"))]
+impl AsRawFd for TlsStream<TcpStream> {
+    fn as_raw_fd(&self) -> RawFd {
+        std::os::unix::io::AsRawFd::as_raw_fd(self.get_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_trailing_literal() {
+        assert_eq!(trailing_literal_len(b"* 4 EXISTS\r\n"), None);
+    }
+
+    #[test]
+    fn trailing_literal_is_parsed() {
+        assert_eq!(
+            trailing_literal_len(b"* 12 FETCH (BODY[] {123}\r\n"),
+            Some(123)
+        );
+    }
+
+    /// A fake transport that plays back a fixed script of bytes and simulated read timeouts, so
+    /// `poll_once` can be exercised without a real socket.
+    #[derive(Default)]
+    struct MockStream {
+        script: std::collections::VecDeque<MockEvent>,
+        written: Vec<u8>,
+    }
+
+    enum MockEvent {
+        Bytes(Vec<u8>),
+        Timeout,
+    }
+
+    impl MockStream {
+        fn push_line(&mut self, line: &str) {
+            self.script
+                .push_back(MockEvent::Bytes(line.as_bytes().to_vec()));
+        }
+
+        fn push_timeout(&mut self) {
+            self.script.push_back(MockEvent::Timeout);
+        }
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.script.front_mut() {
+                None => Ok(0),
+                Some(MockEvent::Timeout) => {
+                    self.script.pop_front();
+                    Err(io::Error::new(io::ErrorKind::TimedOut, "mock timeout"))
+                }
+                Some(MockEvent::Bytes(bytes)) => {
+                    let n = 1.min(bytes.len());
+                    buf[0] = bytes.remove(0);
+                    if bytes.is_empty() {
+                        self.script.pop_front();
+                    }
+                    Ok(n)
+                }
+            }
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SetReadTimeout for MockStream {
+        fn set_read_timeout(&mut self, _timeout: Option<Duration>) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_timeout(&self) -> Result<Option<Duration>> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn poll_once_preserves_responses_seen_before_a_timeout() {
+        let mut stream = MockStream::default();
+        stream.push_line("* 5 EXISTS\r\n");
+        stream.push_timeout();
+        stream.push_line("a1 OK NOOP completed\r\n");
+
+        let mut session = Session::new(stream);
+        match session.poll_once().unwrap() {
+            Stop::Data(responses) => {
+                assert_eq!(responses.len(), 1);
+                assert!(matches!(responses[0], UnsolicitedResponse::Exists(5)));
+            }
+            other => panic!("expected Stop::Data, got {:?}", other),
+        }
+    }
+}
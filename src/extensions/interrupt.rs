@@ -0,0 +1,155 @@
+//! Support for waking a blocked [`Handle`](super::idle::Handle) from another thread.
+//!
+//! [`Handle::wait_with_interrupt`](super::idle::Handle::wait_with_interrupt) normally has no way
+//! to be woken except by server traffic or its own timeout. Pairing it with an [`Interrupt`]
+//! additionally wakes the wait as soon as the matching [`Interruptor::stop`] is called, e.g.
+//! because the user asked for an immediate sync, or the process is shutting down.
+//!
+//! This is currently only implemented on Unix, where it's backed by a self-pipe that gets
+//! polled alongside the IMAP socket.
+
+#[cfg(unix)]
+use crate::error::{Error, Result};
+#[cfg(unix)]
+use std::io;
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+#[cfg(unix)]
+use std::sync::Arc;
+
+/// The write end of the self-pipe, closed once every [`Interrupt`] and [`Interruptor`] clone
+/// sharing it has been dropped.
+///
+/// This is kept behind an [`Arc`] rather than owned directly by [`Interrupt`] because
+/// `Interruptor` is meant to outlive the `Interrupt` it signals -- the documented usage is to
+/// keep the `Interrupt` on the idling thread while handing `Interruptor` clones to other
+/// threads. Closing the write end as soon as `Interrupt` drops would let the OS reuse that fd
+/// number for something unrelated, and a later `Interruptor::stop` call would then write into
+/// that unrelated file instead of failing cleanly.
+#[cfg(unix)]
+#[derive(Debug)]
+struct WriteEnd(RawFd);
+
+#[cfg(unix)]
+impl Drop for WriteEnd {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+/// A one-shot wakeup source that can be used together with
+/// [`Handle::wait_with_interrupt`](super::idle::Handle::wait_with_interrupt).
+///
+/// Create a pair with [`Interrupt::new`]; keep the [`Interrupt`] for the thread that's idling,
+/// and hand the [`Interruptor`] to whoever needs to be able to cancel the wait.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct Interrupt {
+    pub(crate) read_fd: RawFd,
+    // Never read directly -- this `Arc` clone exists purely to keep the write end open for as
+    // long as this `Interrupt` is alive, matching the clone held by every `Interruptor`.
+    #[allow(dead_code)]
+    write_fd: Arc<WriteEnd>,
+}
+
+/// A cloneable handle used to signal an [`Interrupt`] from another thread.
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub struct Interruptor {
+    write_fd: Arc<WriteEnd>,
+}
+
+#[cfg(unix)]
+impl Interrupt {
+    /// Create a new interrupt source, together with the [`Interruptor`] used to signal it.
+    pub fn new() -> Result<(Self, Interruptor)> {
+        let mut fds = [0 as RawFd; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+        let (read_fd, write_fd) = (fds[0], fds[1]);
+
+        // The read end needs to be non-blocking: `wait_with_interrupt` drains it in a loop after
+        // `poll` reports it readable, and that drain must be able to stop at "no more bytes"
+        // rather than blocking on a read once it catches up.
+        let flags = unsafe { libc::fcntl(read_fd, libc::F_GETFL) };
+        if flags < 0 || unsafe { libc::fcntl(read_fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0
+        {
+            let err = Err(Error::Io(io::Error::last_os_error()));
+            unsafe {
+                libc::close(read_fd);
+                libc::close(write_fd);
+            }
+            return err;
+        }
+
+        let write_fd = Arc::new(WriteEnd(write_fd));
+        Ok((
+            Interrupt {
+                read_fd,
+                write_fd: write_fd.clone(),
+            },
+            Interruptor { write_fd },
+        ))
+    }
+}
+
+#[cfg(unix)]
+impl Interruptor {
+    /// Wake the [`Handle::wait_with_interrupt`](super::idle::Handle::wait_with_interrupt) call
+    /// that is using the matching [`Interrupt`], if any.
+    ///
+    /// This can be called from any thread, at any time, including before the wait has started
+    /// (in which case the next wait returns immediately), and even after the matching
+    /// [`Interrupt`] has been dropped -- in that case the write end is still open (kept alive by
+    /// this `Interruptor`) while the read end is closed, so the call fails with a normal
+    /// broken-pipe I/O error, rather than writing into an unrelated, reused file descriptor.
+    pub fn stop(&self) -> Result<()> {
+        let byte = [1u8];
+        if unsafe { libc::write(self.write_fd.0, byte.as_ptr() as *const _, 1) } < 0 {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Interrupt {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.read_fd);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_after_interrupt_dropped_does_not_corrupt_an_unrelated_fd() {
+        let (interrupt, interruptor) = Interrupt::new().unwrap();
+        drop(interrupt);
+
+        // Open an unrelated file now, so that if the write end's fd number had been closed (and
+        // thus made available for reuse) by dropping `Interrupt` above, this call would be the
+        // one to reuse it.
+        let canary = std::fs::File::open("/dev/null").unwrap();
+        let canary_fd = std::os::unix::io::AsRawFd::as_raw_fd(&canary);
+
+        // The write end is kept alive by `interruptor`'s `Arc`, so the byte goes into the pipe,
+        // whose read end is gone: the call fails with a normal broken-pipe error and the canary
+        // file is never written to.
+        match interruptor.stop() {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::BrokenPipe),
+            other => panic!("expected a broken-pipe error, got {:?}", other),
+        }
+
+        assert_ne!(
+            interruptor.write_fd.0, canary_fd,
+            "canary fd should not reuse the pipe's write end"
+        );
+    }
+}
@@ -0,0 +1,4 @@
+//! IMAP extensions beyond the core protocol defined in RFC 3501.
+
+pub mod idle;
+pub mod interrupt;
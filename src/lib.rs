@@ -0,0 +1,19 @@
+//! # imap
+//!
+//! This crate lets you connect to and interact with servers that implement the IMAP protocol
+//! ([RFC 3501](https://tools.ietf.org/html/rfc3501)), with support for the `IDLE` extension
+//! ([RFC 2177](https://tools.ietf.org/html/rfc2177)).
+
+pub mod client;
+pub mod error;
+pub mod extensions;
+pub mod types;
+
+pub use crate::client::{Client, Session};
+pub use crate::error::{Error, Result};
+
+#[cfg(feature = "tls")]
+pub use crate::client::{connect, connect_timeout};
+
+#[cfg(feature = "rustls-tls")]
+pub use crate::client::{connect_rustls, connect_rustls_timeout};
@@ -0,0 +1,134 @@
+//! Types used to represent data returned by the IMAP server.
+
+/// An unsolicited, untagged response sent by the server outside of a tagged command reply.
+///
+/// These correspond to the untagged responses described in [RFC 3501 section
+/// 7](https://tools.ietf.org/html/rfc3501#section-7) that a server may send at any point to
+/// notify the client that the state of the selected mailbox has changed. They're most commonly
+/// seen while a [`Handle`](crate::extensions::idle::Handle) is idling, but can in principle show
+/// up as part of any response.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum UnsolicitedResponse {
+    /// The number of messages in the mailbox; `* <n> EXISTS`.
+    Exists(u32),
+    /// A message has been permanently removed from the mailbox; `* <n> EXPUNGE`.
+    Expunge(u32),
+    /// The number of messages with the `\Recent` flag set; `* <n> RECENT`.
+    Recent(u32),
+    /// The flags (or other attributes) of a single message changed; `* <n> FETCH (...)`.
+    Fetch {
+        /// The message sequence number the `FETCH` response is about.
+        id: u32,
+        /// The raw attribute list the server sent, e.g. `(FLAGS (\Seen))`.
+        attrs: String,
+    },
+    /// Any other untagged response we don't have a dedicated variant for, kept verbatim so
+    /// callers can still inspect it.
+    Unknown(String),
+}
+
+impl UnsolicitedResponse {
+    /// Parse a single untagged (`* ...`) line as sent by the server, e.g. while idling.
+    ///
+    /// Returns `None` if `line` is not an untagged response, or is one that carries no
+    /// mailbox-change information (e.g. the `* OK Still here` keepalive some servers send, or a
+    /// `* BYE ...` notice) and so should not wake an idling client.
+    pub(crate) fn parse(line: &[u8]) -> Option<Self> {
+        let line = std::str::from_utf8(line).ok()?.trim_end();
+        let rest = line.strip_prefix("* ")?;
+
+        if rest.starts_with("OK") || rest.starts_with("BYE") {
+            return None;
+        }
+
+        let mut parts = rest.splitn(2, ' ');
+        let first = parts.next().unwrap_or("");
+        let rem = parts.next().unwrap_or("").trim();
+
+        match first.parse::<u32>() {
+            Ok(id) => {
+                let kw = rem.split_whitespace().next().unwrap_or("");
+                match kw {
+                    "EXISTS" => Some(UnsolicitedResponse::Exists(id)),
+                    "EXPUNGE" => Some(UnsolicitedResponse::Expunge(id)),
+                    "RECENT" => Some(UnsolicitedResponse::Recent(id)),
+                    "FETCH" => Some(UnsolicitedResponse::Fetch {
+                        id,
+                        attrs: rem[kw.len()..].trim().to_string(),
+                    }),
+                    _ => Some(UnsolicitedResponse::Unknown(line.to_string())),
+                }
+            }
+            Err(_) => Some(UnsolicitedResponse::Unknown(line.to_string())),
+        }
+    }
+}
+
+/// The capabilities advertised by the server, as returned by `Session::capabilities`.
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities(pub(crate) Vec<String>);
+
+impl Capabilities {
+    /// Whether the server advertised the given capability, e.g. `"IDLE"`.
+    ///
+    /// The comparison is case-insensitive, as capability names are in
+    /// [RFC 3501](https://tools.ietf.org/html/rfc3501#section-6.1.1).
+    pub fn has_str(&self, name: &str) -> bool {
+        self.0.iter().any(|c| c.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_exists_expunge_recent() {
+        assert_eq!(
+            UnsolicitedResponse::parse(b"* 23 EXISTS\r\n"),
+            Some(UnsolicitedResponse::Exists(23))
+        );
+        assert_eq!(
+            UnsolicitedResponse::parse(b"* 3 EXPUNGE\r\n"),
+            Some(UnsolicitedResponse::Expunge(3))
+        );
+        assert_eq!(
+            UnsolicitedResponse::parse(b"* 5 RECENT\r\n"),
+            Some(UnsolicitedResponse::Recent(5))
+        );
+    }
+
+    #[test]
+    fn parses_fetch_with_attrs() {
+        assert_eq!(
+            UnsolicitedResponse::parse(b"* 12 FETCH (FLAGS (\\Seen))\r\n"),
+            Some(UnsolicitedResponse::Fetch {
+                id: 12,
+                attrs: "(FLAGS (\\Seen))".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_keepalive_and_bye() {
+        assert_eq!(UnsolicitedResponse::parse(b"* OK Still here\r\n"), None);
+        assert_eq!(UnsolicitedResponse::parse(b"* BYE logging out\r\n"), None);
+    }
+
+    #[test]
+    fn capabilities_has_str_is_case_insensitive() {
+        let caps = Capabilities(vec!["IMAP4rev1".to_string(), "IDLE".to_string()]);
+        assert!(caps.has_str("idle"));
+        assert!(!caps.has_str("STARTTLS"));
+    }
+
+    #[test]
+    fn falls_back_to_unknown() {
+        assert_eq!(
+            UnsolicitedResponse::parse(b"* CAPABILITY IMAP4rev1 IDLE\r\n"),
+            Some(UnsolicitedResponse::Unknown(
+                "* CAPABILITY IMAP4rev1 IDLE".to_string()
+            ))
+        );
+    }
+}